@@ -0,0 +1,116 @@
+//! Apache Arrow interop: pulling query results into a
+//! [`RecordBatch`](arrow_array::RecordBatch) for handoff to Polars,
+//! DataFusion, or anything else speaking Arrow. Enabled by the `arrow`
+//! feature.
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BinaryArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use rusqlite::{types::Value, Connection};
+
+use crate::{RusqliteHelperError, Table};
+
+/// Build an Arrow array from one column of SQLite values. SQLite columns
+/// are dynamically typed, so the array type is inferred from the values:
+/// all-integer becomes `Int64`, numeric with any float becomes `Float64`
+/// (SQLite's own affinity behavior), text `Utf8`, blobs `Binary`, and an
+/// all-NULL column the `Null` type. Mixing storage classes beyond
+/// integer/float in one column is an error.
+fn column_to_array(name: &str, values: &[Value]) -> Result<ArrayRef, RusqliteHelperError> {
+    let (mut ints, mut reals, mut texts, mut blobs) = (false, false, false, false);
+    for value in values {
+        match value {
+            Value::Null => {}
+            Value::Integer(_) => ints = true,
+            Value::Real(_) => reals = true,
+            Value::Text(_) => texts = true,
+            Value::Blob(_) => blobs = true,
+        }
+    }
+    let array: ArrayRef = match (ints, reals, texts, blobs) {
+        (false, false, false, false) => {
+            arrow_array::new_null_array(&arrow_schema::DataType::Null, values.len())
+        }
+        (true, false, false, false) => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Integer(i) => Some(*i),
+                    _ => None,
+                })
+                .collect::<Int64Array>(),
+        ),
+        (_, true, false, false) => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Integer(i) => Some(*i as f64),
+                    Value::Real(r) => Some(*r),
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        (false, false, true, false) => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Text(t) => Some(t.as_str()),
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+        (false, false, false, true) => Arc::new(BinaryArray::from_opt_vec(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Blob(b) => Some(b.as_slice()),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        _ => {
+            return Err(RusqliteHelperError::Arrow(format!(
+                "column {name} mixes storage classes"
+            )))
+        }
+    };
+    Ok(array)
+}
+
+impl Table {
+    /// Query rows into an Arrow [`RecordBatch`] instead of deserializing
+    /// them into structs — zero-copy interop with the Arrow ecosystem from
+    /// there on. Column types are inferred from the fetched values (see
+    /// [`column_to_array`]'s rules); the whole result is materialized
+    /// before conversion, so bound it with a WHERE clause on big tables.
+    pub fn query_arrow(
+        &self,
+        c: &Connection,
+        where_stmt: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<RecordBatch, RusqliteHelperError> {
+        let name = &self.qualified_name();
+        let sql = format!("SELECT * FROM {name} {where_stmt};");
+        trace!("{sql}");
+        let mut stmt = c.prepare(&sql)?;
+        let column_names = stmt
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut columns: Vec<Vec<Value>> = vec![Vec::new(); column_names.len()];
+        let mut rows = stmt.query(params)?;
+        while let Some(row) = rows.next()? {
+            for (i, column) in columns.iter_mut().enumerate() {
+                column.push(row.get_ref(i)?.into());
+            }
+        }
+        let arrays = column_names
+            .iter()
+            .zip(&columns)
+            .map(|(name, values)| Ok((name.clone(), column_to_array(name, values)?)))
+            .collect::<Result<Vec<_>, RusqliteHelperError>>()?;
+        RecordBatch::try_from_iter(arrays)
+            .map_err(|e| RusqliteHelperError::Arrow(e.to_string()))
+    }
+}
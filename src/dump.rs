@@ -0,0 +1,116 @@
+//! Portable `.sql` dumps in the style of the `sqlite3 .dump` command:
+//! plain-text CREATE and INSERT statements that any SQLite build can
+//! replay. See [`dump_sql`].
+
+use std::io::{Read, Write};
+
+use rusqlite::{types::ValueRef, Connection};
+
+use crate::RusqliteHelperError;
+
+/// A value as a self-contained SQL literal: strings with quotes doubled,
+/// BLOBs as `X'..'` hex literals.
+fn sql_literal(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(r) => {
+            // SQLite can store +/-Infinity but has no literal for it;
+            // `sqlite3 .dump` writes an overflowing constant instead,
+            // which parses back to the infinity.
+            if r.is_infinite() {
+                return if r > 0.0 { "9e999" } else { "-9e999" }.to_string();
+            }
+            // Default float formatting round-trips, but suppresses the
+            // decimal point for integral values, which would read back as
+            // an INTEGER.
+            let formatted = r.to_string();
+            if formatted.contains(['.', 'e', 'n', 'i']) {
+                formatted
+            } else {
+                format!("{formatted}.0")
+            }
+        }
+        ValueRef::Text(t) => {
+            let text = String::from_utf8_lossy(t);
+            format!("'{}'", text.replace('\'', "''"))
+        }
+        ValueRef::Blob(b) => {
+            let mut hex = String::with_capacity(b.len() * 2 + 3);
+            hex.push_str("X'");
+            for byte in b {
+                hex.push_str(&format!("{byte:02X}"));
+            }
+            hex.push('\'');
+            hex
+        }
+    }
+}
+
+/// Write the whole database as a `.sql` dump: CREATE statements for every
+/// object and INSERT statements for every row, wrapped in a transaction,
+/// in `sqlite_master` order so dependencies come before their dependents.
+/// Tables and their rows come first, then indexes, triggers, and views —
+/// the same layout `sqlite3 .dump` produces, and loadable by anything that
+/// can execute a SQL script (including [`load_sql`]). SQLite's internal
+/// `sqlite_*` tables are skipped.
+pub fn dump_sql<W: Write>(c: &Connection, writer: &mut W) -> Result<(), RusqliteHelperError> {
+    writeln!(writer, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(writer, "BEGIN TRANSACTION;")?;
+
+    let mut stmt = c.prepare(
+        "SELECT name, sql FROM sqlite_master
+         WHERE type = 'table' AND sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+         ORDER BY rowid;",
+    )?;
+    let tables = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (name, ddl) in tables {
+        trace!("dumping table {name}");
+        writeln!(writer, "{ddl};")?;
+        let quoted = format!("\"{}\"", name.replace('"', "\"\""));
+        let mut stmt = c.prepare(&format!("SELECT * FROM {quoted};"))?;
+        let column_count = stmt.column_count();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let values = (0..column_count)
+                .map(|i| Ok(sql_literal(row.get_ref(i)?)))
+                .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+            writeln!(writer, "INSERT INTO {quoted} VALUES({});", values.join(","))?;
+        }
+    }
+
+    let mut stmt = c.prepare(
+        "SELECT sql FROM sqlite_master
+         WHERE type != 'table' AND sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+         ORDER BY rowid;",
+    )?;
+    let others = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for ddl in others {
+        writeln!(writer, "{ddl};")?;
+    }
+
+    writeln!(writer, "COMMIT;")?;
+    Ok(())
+}
+
+/// Replay a `.sql` dump — the inverse of [`dump_sql`]. The script is handed
+/// to SQLite statement by statement through its own parser (the
+/// prepare-tail mechanism behind `execute_batch`), so statement boundaries
+/// are found correctly even with semicolons inside string literals or
+/// trigger bodies — the cases naive `split(';')` loaders get wrong. The
+/// dump's own `BEGIN TRANSACTION` / `COMMIT` wrapper applies as written, so
+/// a failed load rolls back rather than leaving a half-restored database.
+/// Expects the target tables not to exist yet; load into a fresh database.
+pub fn load_sql<R: Read>(c: &Connection, reader: &mut R) -> Result<(), RusqliteHelperError> {
+    let mut sql = String::new();
+    reader.read_to_string(&mut sql)?;
+    info!("loading SQL dump ({} bytes)", sql.len());
+    c.execute_batch(&sql)?;
+    Ok(())
+}
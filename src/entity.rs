@@ -0,0 +1,64 @@
+//! An active-record-style layer on top of [`Table`]: implement [`Entity`]
+//! for a row struct and saving, loading, or deleting an object is one
+//! line.
+
+use rusqlite::Connection;
+use serde_rusqlite::to_params_named;
+
+use crate::{InsertConflictResolution, RusqliteHelperError, Table};
+
+/// A row struct bound to its [`Table`]. Only [`Entity::table`] and
+/// [`Entity::FIELDS`] need implementing; the operations come as default
+/// methods. All of them run single statements on a `&Connection`, so they
+/// compose with [`with_transaction`](crate::with_transaction) and
+/// savepoints without further ceremony.
+///
+/// ```no_run
+/// # use rusqlite_helper::{Entity, Table};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Account {
+///     acct: String,
+///     name: String,
+/// }
+///
+/// impl Entity for Account {
+///     fn table() -> Table {
+///         Table::new("accounts", "acct TEXT PRIMARY KEY, name TEXT").with_pk("acct")
+///     }
+///     const FIELDS: &'static [&'static str] = &["acct", "name"];
+/// }
+/// ```
+pub trait Entity: serde::Serialize + serde::de::DeserializeOwned {
+    /// The table this type is stored in. Declare the primary key with
+    /// [`Table::with_pk`]; [`Entity::load`] and [`Entity::delete`] need it.
+    fn table() -> Table;
+
+    /// The columns [`Entity::save`] writes — the serialized field names,
+    /// matching any `#[serde(rename)]` attributes.
+    const FIELDS: &'static [&'static str];
+
+    /// Upsert this object (insert, replacing an existing row with the same
+    /// primary key). Returns false if the write was a no-op.
+    fn save(&self, c: &Connection) -> Result<bool, RusqliteHelperError> {
+        Self::table().insert(c, self, Self::FIELDS, InsertConflictResolution::Replace)
+    }
+
+    /// Load the object whose primary key equals `key`.
+    fn load(c: &Connection, key: impl rusqlite::ToSql) -> Result<Option<Self>, RusqliteHelperError> {
+        Self::table().load_by_pk(c, key)
+    }
+
+    /// Delete this object's row, identified by its primary-key field.
+    /// Returns false when no row with that key existed.
+    fn delete(&self, c: &Connection) -> Result<bool, RusqliteHelperError> {
+        let table = Self::table();
+        let pk = table.pk_column()?.to_string();
+        let row_params = to_params_named(self)?;
+        let params = row_params.to_slice();
+        let (_, key) = params
+            .iter()
+            .find(|(name, _)| name.trim_start_matches(':') == pk)
+            .ok_or_else(|| RusqliteHelperError::MissingFields(vec![pk.clone()]))?;
+        table.delete_by_pk(c, key)
+    }
+}
@@ -0,0 +1,111 @@
+//! A junction table managing a many-to-many relationship between two
+//! entities, built on top of [`Table`].
+
+use rusqlite::Connection;
+
+use crate::{RusqliteHelperError, Table};
+
+/// A link table with two foreign-key columns and a composite primary key.
+/// The underlying [`Table`] is exposed as `table` so the usual `create` /
+/// `query` machinery applies.
+pub struct JoinTable {
+    pub table: Table,
+    pub a_column: String,
+    pub b_column: String,
+}
+
+impl JoinTable {
+    /// E.g. `JoinTable::new("account_tags", "acct", "accounts(acct)", "tag", "tags(name)")`.
+    pub fn new(
+        name: impl ToString,
+        a_column: impl ToString,
+        a_references: &str,
+        b_column: impl ToString,
+        b_references: &str,
+    ) -> Self {
+        let a_column = a_column.to_string();
+        let b_column = b_column.to_string();
+        let def = format!(
+            "{a_column} NOT NULL REFERENCES {a_references},
+             {b_column} NOT NULL REFERENCES {b_references},
+             PRIMARY KEY ({a_column}, {b_column})"
+        );
+        Self {
+            table: Table::new(name, def),
+            a_column,
+            b_column,
+        }
+    }
+
+    /// Link `a` and `b`, returning false if the link already existed.
+    pub fn link(
+        &self,
+        c: &Connection,
+        a: impl rusqlite::ToSql,
+        b: impl rusqlite::ToSql,
+    ) -> Result<bool, RusqliteHelperError> {
+        let Self {
+            table: Table { name, .. },
+            a_column,
+            b_column,
+        } = self;
+        let sql = format!("INSERT OR IGNORE INTO {name} ({a_column}, {b_column}) VALUES (?, ?)");
+        trace!("{sql}");
+        let n = c.execute(&sql, rusqlite::params![a, b])?;
+        Ok(n != 0)
+    }
+
+    /// Remove the link between `a` and `b`, returning false if it did not exist.
+    pub fn unlink(
+        &self,
+        c: &Connection,
+        a: impl rusqlite::ToSql,
+        b: impl rusqlite::ToSql,
+    ) -> Result<bool, RusqliteHelperError> {
+        let Self {
+            table: Table { name, .. },
+            a_column,
+            b_column,
+        } = self;
+        let sql = format!("DELETE FROM {name} WHERE {a_column} = ? AND {b_column} = ?");
+        trace!("{sql}");
+        let n = c.execute(&sql, rusqlite::params![a, b])?;
+        Ok(n != 0)
+    }
+
+    /// All `b` ids linked to `a`.
+    pub fn related_ids<B: rusqlite::types::FromSql>(
+        &self,
+        c: &Connection,
+        a: impl rusqlite::ToSql,
+    ) -> Result<Vec<B>, RusqliteHelperError> {
+        let Self {
+            table: Table { name, .. },
+            a_column,
+            b_column,
+        } = self;
+        let sql = format!("SELECT {b_column} FROM {name} WHERE {a_column} = ?");
+        trace!("{sql}");
+        let mut stmt = c.prepare(&sql)?;
+        let rows = stmt.query_map([a], |row| row.get(0))?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// All `a` ids linked to `b` — the reverse direction of [`JoinTable::related_ids`].
+    pub fn related_ids_reverse<A: rusqlite::types::FromSql>(
+        &self,
+        c: &Connection,
+        b: impl rusqlite::ToSql,
+    ) -> Result<Vec<A>, RusqliteHelperError> {
+        let Self {
+            table: Table { name, .. },
+            a_column,
+            b_column,
+        } = self;
+        let sql = format!("SELECT {a_column} FROM {name} WHERE {b_column} = ?");
+        trace!("{sql}");
+        let mut stmt = c.prepare(&sql)?;
+        let rows = stmt.query_map([b], |row| row.get(0))?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+}
@@ -0,0 +1,80 @@
+//! Routing reads and writes to different connections for primary/replica
+//! deployments.
+
+use rusqlite::Connection;
+
+use crate::{InsertConflictResolution, RusqliteHelperError, Table};
+
+/// Hands out the right connection per operation class: query-type methods
+/// pull a read connection (a replica), mutating methods a write connection
+/// (the primary). The crate manages no pools — implement this on top of
+/// whatever pooling is in use and return borrows of checked-out
+/// connections. A single-database setup can return the same connection
+/// from both methods.
+pub trait ConnectionRouter {
+    fn read(&self) -> &Connection;
+    fn write(&self) -> &Connection;
+}
+
+/// A [`Table`] bound to a [`ConnectionRouter`], obtained via
+/// [`Table::routed`]: the common CRUD methods without the connection
+/// argument, each pulling the appropriate side from the router. For
+/// anything not mirrored here, pull the connection explicitly —
+/// `table.query_windowed(router.read(), ...)` — the classification is the
+/// method's own read/write nature.
+pub struct Routed<'a, R: ConnectionRouter> {
+    table: &'a Table,
+    router: &'a R,
+}
+
+impl<'a, R: ConnectionRouter> Routed<'a, R> {
+    pub(crate) fn new(table: &'a Table, router: &'a R) -> Self {
+        Routed { table, router }
+    }
+}
+
+impl<R: ConnectionRouter> Routed<'_, R> {
+    /// [`Table::query`] on a read connection.
+    pub fn query<D: serde::de::DeserializeOwned>(
+        &self,
+        where_stmt: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<D>, RusqliteHelperError> {
+        self.table.query(self.router.read(), where_stmt, params)
+    }
+
+    /// [`Table::load_by_pk`] on a read connection.
+    pub fn load_by_pk<D: serde::de::DeserializeOwned>(
+        &self,
+        key: impl rusqlite::ToSql,
+    ) -> Result<Option<D>, RusqliteHelperError> {
+        self.table.load_by_pk(self.router.read(), key)
+    }
+
+    /// [`Table::insert`] on the write connection.
+    pub fn insert(
+        &self,
+        row: impl serde::Serialize,
+        fields: &[&str],
+        conflict: InsertConflictResolution<'_>,
+    ) -> Result<bool, RusqliteHelperError> {
+        self.table
+            .insert(self.router.write(), row, fields, conflict)
+    }
+
+    /// [`Table::insert_many`] on the write connection.
+    pub fn insert_many<T: serde::Serialize>(
+        &self,
+        rows: impl IntoIterator<Item = T>,
+        fields: &[&str],
+        conflict: InsertConflictResolution<'_>,
+    ) -> Result<usize, RusqliteHelperError> {
+        self.table
+            .insert_many(self.router.write(), rows, fields, conflict)
+    }
+
+    /// [`Table::delete_by_pk`] on the write connection.
+    pub fn delete_by_pk(&self, key: impl rusqlite::ToSql) -> Result<bool, RusqliteHelperError> {
+        self.table.delete_by_pk(self.router.write(), key)
+    }
+}
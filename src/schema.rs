@@ -0,0 +1,336 @@
+//! Comparing a [`Table`]'s declared `def` against what a database actually
+//! contains. The `def` string is parsed into per-column declarations which
+//! are matched by name against the stored DDL.
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::{RusqliteHelperError, Table};
+
+/// One column of a table definition: its name and the rest of the
+/// declaration (type and column constraints), whitespace-normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub decl: String,
+}
+
+/// Structured difference between a [`Table::def`] and the schema stored in a
+/// database, as produced by [`diff_schema`].
+#[derive(Debug, Default)]
+pub struct SchemaDiff {
+    /// Columns declared in `def` but missing from the database.
+    pub added: Vec<ColumnDef>,
+    /// Columns present in the database but not declared in `def`.
+    pub removed: Vec<ColumnDef>,
+    /// Columns present in both but with differing declarations, as
+    /// `(database, declared)` pairs.
+    pub changed: Vec<(ColumnDef, ColumnDef)>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Status of one table in a [`HealthReport`].
+#[derive(Debug)]
+pub enum TableHealth {
+    /// Exists and matches its declared `def`.
+    Present,
+    /// Does not exist in the database.
+    Missing,
+    /// Exists but differs from the declared `def`.
+    Drifted(SchemaDiff),
+}
+
+/// Result of [`healthcheck`]: every checked table's name paired with its
+/// status.
+#[derive(Debug, Default)]
+pub struct HealthReport {
+    pub tables: Vec<(String, TableHealth)>,
+}
+
+impl HealthReport {
+    /// True when every table is present with a matching schema.
+    pub fn is_healthy(&self) -> bool {
+        self.tables
+            .iter()
+            .all(|(_, health)| matches!(health, TableHealth::Present))
+    }
+}
+
+/// Verify that each of `tables` exists and matches its declared schema —
+/// the one-call check a service health endpoint wants. Ties together
+/// existence detection and [`diff_schema`]; a table counts as
+/// [`TableHealth::Drifted`] as soon as any column was added, removed, or
+/// changed relative to its [`Table::def`].
+pub fn healthcheck(
+    c: &Connection,
+    tables: &[&Table],
+) -> Result<HealthReport, RusqliteHelperError> {
+    let mut report = HealthReport::default();
+    for table in tables {
+        let health = match stored_ddl(c, table.schema.as_deref(), &table.name)? {
+            None => TableHealth::Missing,
+            Some(_) => {
+                let diff = diff_schema(c, table)?;
+                if diff.is_empty() {
+                    TableHealth::Present
+                } else {
+                    TableHealth::Drifted(diff)
+                }
+            }
+        };
+        report.tables.push((table.name.clone(), health));
+    }
+    Ok(report)
+}
+
+/// The `CREATE TABLE` statement SQLite stored for `name`, or `None` if the
+/// table does not exist. `schema` selects the `sqlite_master` of an
+/// attached database.
+pub(crate) fn stored_ddl(
+    c: &Connection,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<Option<String>, RusqliteHelperError> {
+    let master = match schema {
+        Some(schema) => format!("{schema}.sqlite_master"),
+        None => "sqlite_master".to_string(),
+    };
+    Ok(c.query_row(
+        &format!("SELECT sql FROM {master} WHERE type = 'table' AND name = ?;"),
+        [name],
+        |row| row.get(0),
+    )
+    .optional()?)
+}
+
+/// Replicate the schema of `src` on `dst` without copying any rows: all
+/// `sqlite_master` entries (tables, indexes, triggers, views) are executed
+/// on `dst` in the order they were created, which keeps dependencies
+/// intact. SQLite's internal tables are skipped.
+pub fn clone_schema(src: &Connection, dst: &Connection) -> Result<(), RusqliteHelperError> {
+    let mut stmt = src.prepare(
+        "SELECT sql FROM sqlite_master
+         WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+         ORDER BY rowid;",
+    )?;
+    let ddls = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for ddl in ddls {
+        trace!("{ddl}");
+        dst.execute_batch(&ddl)?;
+    }
+    Ok(())
+}
+
+/// Split a column definition list on top-level commas, respecting
+/// parentheses and quoted strings.
+pub(crate) fn split_top_level(def: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for ch in def.chars() {
+        match quote {
+            Some(q) => {
+                current.push(ch);
+                if ch == q {
+                    quote = None;
+                }
+            }
+            None => match ch {
+                '\'' | '"' | '`' => {
+                    quote = Some(ch);
+                    current.push(ch);
+                }
+                '(' => {
+                    depth += 1;
+                    current.push(ch);
+                }
+                ')' => {
+                    depth = depth.saturating_sub(1);
+                    current.push(ch);
+                }
+                ',' if depth == 0 => {
+                    parts.push(std::mem::take(&mut current));
+                }
+                _ => current.push(ch),
+            },
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+const TABLE_CONSTRAINTS: &[&str] = &["PRIMARY", "FOREIGN", "UNIQUE", "CHECK", "CONSTRAINT"];
+
+/// Collapse whitespace and lowercase everything outside quoted sections.
+fn normalize_item(item: &str) -> String {
+    let mut out = String::new();
+    let mut quote: Option<char> = None;
+    let mut last_ws = true;
+    for ch in item.chars() {
+        match quote {
+            Some(q) => {
+                out.push(ch);
+                if ch == q {
+                    quote = None;
+                }
+            }
+            None if ch.is_whitespace() => {
+                if !last_ws {
+                    out.push(' ');
+                    last_ws = true;
+                }
+            }
+            None => {
+                last_ws = false;
+                if matches!(ch, '\'' | '"' | '`') {
+                    quote = Some(ch);
+                    out.push(ch);
+                } else {
+                    out.push(ch.to_ascii_lowercase());
+                }
+            }
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// The canonical form of a column definition list: whitespace collapsed,
+/// keywords and identifiers lowercased (quoted literals untouched), table
+/// constraints sorted and moved after the columns. Two `def` strings that
+/// describe the same schema normalize to the same string, which is what the
+/// drift-detection helpers compare. See [`Table::normalized_def`].
+pub fn normalize_def(def: &str) -> String {
+    let mut columns = Vec::new();
+    let mut constraints = Vec::new();
+    for item in split_top_level(def) {
+        let item = normalize_item(&item);
+        if item.is_empty() {
+            continue;
+        }
+        let first = item.split(' ').next().unwrap_or("");
+        if TABLE_CONSTRAINTS
+            .iter()
+            .any(|kw| first.eq_ignore_ascii_case(kw))
+        {
+            constraints.push(item);
+        } else {
+            columns.push(item);
+        }
+    }
+    constraints.sort();
+    columns.extend(constraints);
+    columns.join(", ")
+}
+
+/// Parse a column definition list (the part between the parentheses of a
+/// `CREATE TABLE`) into [`ColumnDef`]s, skipping table-level constraints.
+pub(crate) fn parse_columns(def: &str) -> Vec<ColumnDef> {
+    split_top_level(def)
+        .into_iter()
+        .filter_map(|item| {
+            let item = item.split_whitespace().collect::<Vec<_>>().join(" ");
+            let (name, decl) = match item.split_once(' ') {
+                Some((name, decl)) => (name, decl.to_string()),
+                None => (item.as_str(), String::new()),
+            };
+            if name.is_empty()
+                || TABLE_CONSTRAINTS
+                    .iter()
+                    .any(|kw| name.eq_ignore_ascii_case(kw))
+            {
+                return None;
+            }
+            let name = name.trim_matches(|c| matches!(c, '"' | '\'' | '`' | '[' | ']'));
+            Some(ColumnDef {
+                name: name.to_string(),
+                decl,
+            })
+        })
+        .collect()
+}
+
+/// Extract the column definition list from a stored `CREATE TABLE`
+/// statement.
+pub(crate) fn ddl_body(ddl: &str) -> &str {
+    match (ddl.find('('), ddl.rfind(')')) {
+        (Some(start), Some(end)) if start < end => &ddl[start + 1..end],
+        _ => "",
+    }
+}
+
+/// Compare `table.def` against the schema stored in the database. Columns
+/// are matched by name; declarations are compared whitespace-normalized and
+/// case-insensitively. When the table does not exist in the database, every
+/// declared column shows up as added.
+pub fn diff_schema(c: &Connection, table: &Table) -> Result<SchemaDiff, RusqliteHelperError> {
+    let declared = parse_columns(&table.def);
+    let stored = match stored_ddl(c, table.schema.as_deref(), &table.name)? {
+        Some(ddl) => parse_columns(ddl_body(&ddl)),
+        None => Vec::new(),
+    };
+
+    let mut diff = SchemaDiff::default();
+    for col in &declared {
+        match stored.iter().find(|s| s.name == col.name) {
+            None => diff.added.push(col.clone()),
+            Some(s) if !s.decl.eq_ignore_ascii_case(&col.decl) => {
+                diff.changed.push((s.clone(), col.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    for col in stored {
+        if !declared.iter().any(|d| d.name == col.name) {
+            diff.removed.push(col);
+        }
+    }
+    Ok(diff)
+}
+
+/// Render the database's tables, columns, and foreign keys as a Mermaid
+/// `erDiagram` for documentation. Every table becomes an entity listing its
+/// columns (declared type first, primary key columns marked `PK`), and
+/// every foreign key becomes a many-to-one connection labeled with the
+/// referencing column. Types are reduced to their first word so composite
+/// declarations stay within Mermaid's identifier rules; columns without a
+/// declared type render as `ANY`.
+pub fn schema_to_mermaid(c: &Connection) -> Result<String, RusqliteHelperError> {
+    let mut tables = crate::tables(c)?.into_iter().collect::<Vec<_>>();
+    tables.sort();
+    let mut out = String::from("erDiagram\n");
+    let mut relations = String::new();
+    for table in &tables {
+        out.push_str(&format!("    {table} {{\n"));
+        let mut stmt = c.prepare(&format!("PRAGMA table_info({table});"))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            let ty: String = row.get(2)?;
+            let pk: i64 = row.get(5)?;
+            let ty = ty.split_whitespace().next().unwrap_or("ANY").to_string();
+            let marker = if pk > 0 { " PK" } else { "" };
+            out.push_str(&format!("        {ty} {name}{marker}\n"));
+        }
+        out.push_str("    }\n");
+        let mut stmt = c.prepare(&format!("PRAGMA foreign_key_list({table});"))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let referenced: String = row.get(2)?;
+            let from: String = row.get(3)?;
+            relations.push_str(&format!(
+                "    {table} }}o--|| {referenced} : \"{from}\"\n"
+            ));
+        }
+    }
+    out.push_str(&relations);
+    Ok(out)
+}
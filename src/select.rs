@@ -0,0 +1,164 @@
+//! A fluent builder for SELECT statements against a [`Table`].
+//!
+//! Obtained via [`Table::select`], clauses are accumulated with the builder
+//! methods and the statement is assembled and run by one of the terminal
+//! methods [`Select::fetch`], [`Select::fetch_one`], or [`Select::count`].
+
+use rusqlite::{Connection, ToSql};
+
+use crate::{RusqliteHelperError, Table};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderDir {
+    Asc,
+    Desc,
+}
+
+impl OrderDir {
+    pub(crate) fn as_sql(self) -> &'static str {
+        match self {
+            OrderDir::Asc => "ASC",
+            OrderDir::Desc => "DESC",
+        }
+    }
+}
+
+/// A query prepared once and run many times with different parameters,
+/// obtained from [`Table::prepare_query`]. For hot loops this skips the
+/// per-call SQL generation and re-preparation that [`Table::query`] pays,
+/// and unlike the connection's statement cache the statement is guaranteed
+/// to stay prepared for as long as the handle lives. The result type is
+/// fixed at preparation time.
+pub struct PreparedQuery<'c, D> {
+    stmt: rusqlite::Statement<'c>,
+    _rows: std::marker::PhantomData<D>,
+}
+
+impl<'c, D: serde::de::DeserializeOwned> PreparedQuery<'c, D> {
+    pub(crate) fn new(c: &'c Connection, sql: &str) -> Result<Self, RusqliteHelperError> {
+        Ok(PreparedQuery {
+            stmt: c.prepare(sql)?,
+            _rows: std::marker::PhantomData,
+        })
+    }
+
+    /// Bind `params` and collect the matching rows. The statement is reset
+    /// afterwards, so `run` can be called again immediately.
+    pub fn run(&mut self, params: impl rusqlite::Params) -> Result<Vec<D>, RusqliteHelperError> {
+        let rows = self
+            .stmt
+            .query_and_then(params, serde_rusqlite::from_row::<D>)?;
+        Ok(rows.collect::<Result<Vec<D>, _>>()?)
+    }
+}
+
+pub struct Select<'a> {
+    table: &'a Table,
+    c: &'a Connection,
+    where_clauses: Vec<String>,
+    params: Vec<Box<dyn ToSql + 'a>>,
+    order_by: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl<'a> Select<'a> {
+    pub(crate) fn new(table: &'a Table, c: &'a Connection) -> Self {
+        Self {
+            table,
+            c,
+            where_clauses: Vec::new(),
+            params: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Add a condition with positional `?` placeholders and the parameters
+    /// bound to them. Multiple conditions are joined with `AND`.
+    pub fn where_<P: ToSql + 'a>(
+        mut self,
+        clause: &str,
+        params: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.where_clauses.push(clause.to_string());
+        self.params
+            .extend(params.into_iter().map(|p| Box::new(p) as Box<dyn ToSql + 'a>));
+        self
+    }
+
+    pub fn order_by(mut self, column: &str, dir: OrderDir) -> Self {
+        self.order_by.push(format!("{column} {}", dir.as_sql()));
+        self
+    }
+
+    /// [`Select::order_by`] under an explicit collation, e.g.
+    /// `.order_by_collate("name", "NOCASE", OrderDir::Asc)` for
+    /// case-insensitive sorting regardless of how the column was declared.
+    pub fn order_by_collate(mut self, column: &str, collation: &str, dir: OrderDir) -> Self {
+        self.order_by
+            .push(format!("{column} COLLATE {collation} {}", dir.as_sql()));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn clauses(&self) -> String {
+        let mut sql = String::new();
+        if !self.where_clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.where_clauses.join(" AND "));
+        }
+        if !self.order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order_by.join(", "));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+        sql
+    }
+
+    pub fn fetch<D: serde::de::DeserializeOwned>(self) -> Result<Vec<D>, RusqliteHelperError> {
+        let name = &self.table.qualified_name();
+        let sql = format!(
+            "SELECT {} FROM {name}{};",
+            self.table.select_list(),
+            self.clauses()
+        );
+        trace!("{sql}");
+        let mut stmt = self.c.prepare(&sql)?;
+        let rows = stmt.query_and_then(
+            rusqlite::params_from_iter(self.params.iter().map(|p| p.as_ref())),
+            serde_rusqlite::from_row::<D>,
+        )?;
+        Ok(rows.collect::<Result<Vec<D>, _>>()?)
+    }
+
+    pub fn fetch_one<D: serde::de::DeserializeOwned>(
+        self,
+    ) -> Result<Option<D>, RusqliteHelperError> {
+        Ok(self.limit(1).fetch()?.into_iter().next())
+    }
+
+    /// Run `SELECT COUNT(*)` with the accumulated WHERE clauses; ORDER BY
+    /// and LIMIT are ignored.
+    pub fn count(self) -> Result<i64, RusqliteHelperError> {
+        let name = &self.table.qualified_name();
+        let mut sql = format!("SELECT COUNT(*) FROM {name}");
+        if !self.where_clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.where_clauses.join(" AND "));
+        }
+        sql.push(';');
+        trace!("{sql}");
+        let n = self.c.query_row(
+            &sql,
+            rusqlite::params_from_iter(self.params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )?;
+        Ok(n)
+    }
+}
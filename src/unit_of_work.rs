@@ -0,0 +1,106 @@
+//! Collecting writes across several [`Table`]s and committing them
+//! atomically — the unit-of-work pattern for domain operations that touch
+//! more than one table.
+
+use rusqlite::Connection;
+
+use crate::{with_transaction, RusqliteHelperError, Table, TransactionBehavior};
+
+type QueuedOp = Box<dyn FnOnce(&Connection) -> Result<usize, RusqliteHelperError>>;
+
+/// Queues inserts, updates, and deletes without touching the database,
+/// then runs them all in one transaction on [`UnitOfWork::commit`] — either
+/// every queued operation applies or none do. Operations run in the order
+/// they were queued. Tables are taken by value (they are cheap to build,
+/// see [`Entity::table`](crate::Entity::table) for the usual source), rows
+/// are moved into the queue.
+///
+/// ```no_run
+/// # use rusqlite_helper::UnitOfWork;
+/// # let mut c = rusqlite::Connection::open_in_memory().unwrap();
+/// # let (accounts, tags) = (rusqlite_helper::Table::new("a", "x"), rusqlite_helper::Table::new("b", "y"));
+/// # let (account, tag) = ((), ());
+/// let mut uow = UnitOfWork::new();
+/// uow.insert(accounts, account, &["acct", "name"]);
+/// uow.insert(tags, tag, &["acct", "tag"]);
+/// uow.delete(rusqlite_helper::Table::new("pending", "acct"), "WHERE acct = ?", vec![Box::new("a@b")]);
+/// uow.commit(&mut c)?;
+/// # Ok::<(), rusqlite_helper::RusqliteHelperError>(())
+/// ```
+#[derive(Default)]
+pub struct UnitOfWork {
+    ops: Vec<QueuedOp>,
+}
+
+impl UnitOfWork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an insert of `row`, using the table's default conflict
+    /// resolution (see [`Table::with_default_conflict`]).
+    pub fn insert<R: serde::Serialize + 'static>(
+        &mut self,
+        table: Table,
+        row: R,
+        fields: &[&str],
+    ) -> &mut Self {
+        let fields = fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        self.ops.push(Box::new(move |c| {
+            let fields = fields.iter().map(String::as_str).collect::<Vec<_>>();
+            Ok(table.insert_default(c, row, &fields)? as usize)
+        }));
+        self
+    }
+
+    /// Queue `UPDATE {table} SET {set_stmt} {where_stmt}` with the given
+    /// positional parameters.
+    pub fn update(
+        &mut self,
+        table: Table,
+        set_stmt: &str,
+        where_stmt: &str,
+        params: Vec<Box<dyn rusqlite::ToSql>>,
+    ) -> &mut Self {
+        let sql = format!(
+            "UPDATE {} SET {set_stmt} {where_stmt};",
+            table.qualified_name()
+        );
+        self.ops.push(Box::new(move |c| {
+            trace!("{sql}");
+            Ok(c.execute(&sql, rusqlite::params_from_iter(params.iter()))?)
+        }));
+        self
+    }
+
+    /// Queue `DELETE FROM {table} {where_stmt}` with the given positional
+    /// parameters.
+    pub fn delete(
+        &mut self,
+        table: Table,
+        where_stmt: &str,
+        params: Vec<Box<dyn rusqlite::ToSql>>,
+    ) -> &mut Self {
+        let sql = format!("DELETE FROM {} {where_stmt};", table.qualified_name());
+        self.ops.push(Box::new(move |c| {
+            trace!("{sql}");
+            Ok(c.execute(&sql, rusqlite::params_from_iter(params.iter()))?)
+        }));
+        self
+    }
+
+    /// Run all queued operations inside one IMMEDIATE transaction,
+    /// returning the total number of changed rows. The first failing
+    /// operation rolls everything back.
+    pub fn commit(self, c: &mut Connection) -> Result<usize, RusqliteHelperError> {
+        let ops = self.ops;
+        info!("committing unit of work with {} operations", ops.len());
+        with_transaction(c, TransactionBehavior::Immediate, |tx| {
+            let mut changed = 0;
+            for op in ops {
+                changed += op(tx)?;
+            }
+            Ok(changed)
+        })
+    }
+}
@@ -0,0 +1,60 @@
+//! Tests for the `array` feature: key sets bound through `rarray()` after
+//! [`load_array_module`], and the placeholder fallback when the module was
+//! never loaded.
+
+#![cfg(feature = "array")]
+
+use rusqlite::Connection;
+use rusqlite_helper::{load_array_module, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Item {
+    id: i64,
+    label: String,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new("items", "id INTEGER PRIMARY KEY, label TEXT").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    c.execute_batch(
+        "INSERT INTO items VALUES (1, 'one');
+         INSERT INTO items VALUES (2, 'two');
+         INSERT INTO items VALUES (3, 'three');",
+    )
+    .unwrap();
+    (c, table)
+}
+
+#[test]
+fn query_in_through_rarray() {
+    let (c, table) = setup();
+    load_array_module(&c).unwrap();
+    let mut rows: Vec<Item> = table.query_in(&c, "id", &[1i64, 3]).unwrap();
+    rows.sort_by_key(|row| row.id);
+    assert_eq!(
+        rows.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(table.delete_in(&c, "id", &[2i64]).unwrap(), 1);
+    let remaining: i64 = table.get_scalar(&c, "COUNT(*)", "", []).unwrap();
+    assert_eq!(remaining, 2);
+}
+
+#[test]
+fn query_in_falls_back_without_the_module() {
+    let (c, table) = setup();
+    // No load_array_module: the rarray statement fails to prepare and the
+    // helper retries with one placeholder per key.
+    let rows: Vec<Item> = table.query_in(&c, "id", &[2i64]).unwrap();
+    assert_eq!(
+        rows,
+        vec![Item {
+            id: 2,
+            label: "two".into()
+        }]
+    );
+}
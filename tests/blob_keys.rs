@@ -0,0 +1,67 @@
+//! Tests that the `ToSql`-generic key methods accept BLOB keys
+//! (`&[u8]` / `Vec<u8>`), the case their generic bounds were widened for.
+
+use rusqlite::Connection;
+use rusqlite_helper::Table;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Row {
+    id: i64,
+    label: String,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new("blobs", "id INTEGER PRIMARY KEY, key BLOB, label TEXT").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    c.execute_batch(
+        "INSERT INTO blobs VALUES (1, X'00AA', 'first');
+         INSERT INTO blobs VALUES (2, X'00BB', 'second');
+         INSERT INTO blobs VALUES (3, X'00CC', 'third');",
+    )
+    .unwrap();
+    (c, table)
+}
+
+#[test]
+fn query_in_binds_blob_keys() {
+    let (c, table) = setup();
+    let keys: &[&[u8]] = &[&[0x00, 0xAA], &[0x00, 0xCC]];
+    let mut rows: Vec<Row> = table.query_in(&c, "key", keys).unwrap();
+    rows.sort_by_key(|row| row.id);
+    assert_eq!(
+        rows.iter().map(|r| r.label.as_str()).collect::<Vec<_>>(),
+        vec!["first", "third"]
+    );
+    // Owned keys bind the same way.
+    let owned: Vec<Vec<u8>> = vec![vec![0x00, 0xBB]];
+    let rows: Vec<Row> = table.query_in(&c, "key", &owned).unwrap();
+    assert_eq!(rows, vec![Row { id: 2, label: "second".into() }]);
+}
+
+#[test]
+fn delete_in_binds_blob_keys() {
+    let (c, table) = setup();
+    let keys: &[&[u8]] = &[&[0x00, 0xAA], &[0x00, 0xBB]];
+    let deleted = table.delete_in(&c, "key", keys).unwrap();
+    assert_eq!(deleted, 2);
+    let remaining: i64 = table.get_scalar(&c, "COUNT(*)", "", []).unwrap();
+    assert_eq!(remaining, 1);
+}
+
+#[test]
+fn get_scalar_round_trips_a_blob() {
+    let (c, table) = setup();
+    let key: Vec<u8> = table
+        .get_scalar(&c, "key", "WHERE label = ?", ["second"])
+        .unwrap();
+    assert_eq!(key, vec![0x00, 0xBB]);
+    // ... and that blob can be used as the key of a further lookup.
+    let label: String = table
+        .get_scalar(&c, "label", "WHERE key = ?", [key])
+        .unwrap();
+    assert_eq!(label, "second");
+}
@@ -0,0 +1,89 @@
+//! Tests for the boolean storage and coercion behavior documented in the
+//! crate docs: `bool` fields stored as 0/1 integers, and how foreign data
+//! (non-0/1 integers, floats, text) loads back.
+
+use rusqlite::Connection;
+use rusqlite_helper::{InsertConflictResolution, RusqliteHelperError, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Flag {
+    id: i64,
+    active: bool,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new("flags", "id INTEGER PRIMARY KEY, active INTEGER").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn bools_store_as_zero_and_one() {
+    let (c, table) = setup();
+    for row in [
+        Flag {
+            id: 1,
+            active: false,
+        },
+        Flag { id: 2, active: true },
+    ] {
+        table
+            .insert(&c, row, &["id", "active"], InsertConflictResolution::None)
+            .unwrap();
+    }
+    let stored: Vec<(i64, i64)> = table
+        .query_tuple(&c, &["id", "active"], "ORDER BY id", [])
+        .unwrap();
+    assert_eq!(stored, vec![(1, 0), (2, 1)]);
+    let loaded: Vec<Flag> = table.query(&c, "ORDER BY id", []).unwrap();
+    assert!(!loaded[0].active);
+    assert!(loaded[1].active);
+}
+
+#[test]
+fn nonzero_integers_written_by_other_tools_load_as_true() {
+    let (c, table) = setup();
+    c.execute_batch(
+        "INSERT INTO flags (id, active) VALUES (1, -1);
+         INSERT INTO flags (id, active) VALUES (2, 42);",
+    )
+    .unwrap();
+    let loaded: Vec<Flag> = table.query(&c, "ORDER BY id", []).unwrap();
+    assert!(loaded[0].active);
+    assert!(loaded[1].active);
+}
+
+#[test]
+fn nonzero_float_loads_as_true() {
+    let (c, table) = setup();
+    // An INTEGER column keeps a non-integral REAL as stored.
+    c.execute_batch(
+        "INSERT INTO flags (id, active) VALUES (1, 2.5);
+         INSERT INTO flags (id, active) VALUES (2, 0.0);",
+    )
+    .unwrap();
+    let loaded: Vec<Flag> = table.query(&c, "ORDER BY id", []).unwrap();
+    assert!(loaded[0].active);
+    assert!(!loaded[1].active);
+}
+
+#[test]
+fn text_values_fail_deserialization() {
+    let (c, table) = setup();
+    c.execute_batch("INSERT INTO flags (id, active) VALUES (1, 'true');")
+        .unwrap();
+    let result: Result<Vec<Flag>, _> = table.query(&c, "", []);
+    match result {
+        Err(RusqliteHelperError::WithContext { source, .. }) => {
+            assert!(
+                matches!(*source, RusqliteHelperError::Serialization(_)),
+                "expected a Serialization error, got {source:?}"
+            );
+        }
+        other => panic!("expected a deserialization error, got {other:?}"),
+    }
+}
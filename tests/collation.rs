@@ -0,0 +1,81 @@
+//! Tests for collation support: a column declared NOCASE via
+//! [`Table::with_collation`] matches case-insensitively, and
+//! [`Select::order_by_collate`] applies a per-query collation.
+
+use rusqlite::Connection;
+use rusqlite_helper::{InsertConflictResolution, OrderDir, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: i64,
+    email: String,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new("users", "id INTEGER PRIMARY KEY, email TEXT")
+        .with_pk("id")
+        .with_collation("email", "NOCASE");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn nocase_column_matches_case_insensitively() {
+    let (c, table) = setup();
+    table
+        .insert(
+            &c,
+            User {
+                id: 1,
+                email: "Alice@Example.com".into(),
+            },
+            &["id", "email"],
+            InsertConflictResolution::None,
+        )
+        .unwrap();
+    let found: Vec<User> = table
+        .query(&c, "WHERE email = ?", ["alice@example.com"])
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    // The stored value keeps its original casing; only comparison changes.
+    assert_eq!(found[0].email, "Alice@Example.com");
+}
+
+#[test]
+fn order_by_collate_sorts_case_insensitively() {
+    let c = Connection::open_in_memory().unwrap();
+    // Deliberately no declared collation: the per-query one must do it.
+    let table = Table::new("users", "id INTEGER PRIMARY KEY, email TEXT").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    c.execute_batch(
+        "INSERT INTO users VALUES (1, 'bob@example.com');
+         INSERT INTO users VALUES (2, 'Alice@example.com');",
+    )
+    .unwrap();
+    // BINARY order puts 'Alice' (uppercase) first either way; check that
+    // NOCASE order is alphabetical while plain order is not.
+    let nocase: Vec<User> = table
+        .select(&c)
+        .order_by_collate("email", "NOCASE", OrderDir::Desc)
+        .fetch()
+        .unwrap();
+    assert_eq!(nocase[0].id, 1, "NOCASE DESC puts bob first");
+    let binary: Vec<User> = table
+        .select(&c)
+        .order_by("email", OrderDir::Desc)
+        .fetch()
+        .unwrap();
+    assert_eq!(binary[0].id, 1, "lowercase sorts after uppercase in BINARY");
+    let nocase_asc: Vec<User> = table
+        .select(&c)
+        .order_by_collate("email", "NOCASE", OrderDir::Asc)
+        .fetch()
+        .unwrap();
+    assert_eq!(nocase_asc[0].id, 2, "NOCASE ASC puts Alice first");
+}
@@ -0,0 +1,55 @@
+//! Tests for [`Table::content_hash`]: equal data hashes equal, changed
+//! data hashes differently, and the algorithm is pinned so persisted
+//! hashes stay comparable across releases.
+
+use rusqlite::Connection;
+use rusqlite_helper::Table;
+
+fn setup(c: &Connection) -> Table {
+    let table = Table::new("items", "id INTEGER PRIMARY KEY, name TEXT, score REAL, data BLOB")
+        .with_pk("id");
+    table
+        .create(c, &rusqlite_helper::tables(c).unwrap(), false)
+        .unwrap();
+    c.execute_batch(
+        "INSERT INTO items VALUES (1, 'alice', 0.5, X'DEAD');
+         INSERT INTO items VALUES (2, NULL, 2.0, NULL);",
+    )
+    .unwrap();
+    table
+}
+
+#[test]
+fn identical_data_hashes_identically_across_databases() {
+    let a = Connection::open_in_memory().unwrap();
+    let b = Connection::open_in_memory().unwrap();
+    let table_a = setup(&a);
+    let table_b = setup(&b);
+    assert_eq!(
+        table_a.content_hash(&a, &["id"]).unwrap(),
+        table_b.content_hash(&b, &["id"]).unwrap()
+    );
+}
+
+#[test]
+fn changed_data_changes_the_hash() {
+    let c = Connection::open_in_memory().unwrap();
+    let table = setup(&c);
+    let before = table.content_hash(&c, &["id"]).unwrap();
+    c.execute_batch("UPDATE items SET name = 'bob' WHERE id = 1;")
+        .unwrap();
+    assert_ne!(before, table.content_hash(&c, &["id"]).unwrap());
+}
+
+#[test]
+fn hash_algorithm_is_pinned() {
+    // Guards the FNV-1a implementation: persisted hashes must stay
+    // comparable across releases, so this value may never change for
+    // unchanged data.
+    let c = Connection::open_in_memory().unwrap();
+    let table = setup(&c);
+    assert_eq!(
+        table.content_hash(&c, &["id"]).unwrap(),
+        "f457137a8724a297"
+    );
+}
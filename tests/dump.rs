@@ -0,0 +1,64 @@
+//! Round-trip tests for [`dump_sql`] / [`load_sql`], in particular the
+//! REAL values that need special literal handling.
+
+use rusqlite::Connection;
+use rusqlite_helper::{dump_sql, load_sql};
+
+fn roundtrip(setup_sql: &str) -> Connection {
+    let src = Connection::open_in_memory().unwrap();
+    src.execute_batch(setup_sql).unwrap();
+    let mut dump = Vec::new();
+    dump_sql(&src, &mut dump).unwrap();
+    let dst = Connection::open_in_memory().unwrap();
+    load_sql(&dst, &mut dump.as_slice()).unwrap();
+    dst
+}
+
+#[test]
+fn infinities_survive_a_dump() {
+    let dst = roundtrip(
+        "CREATE TABLE reals (id INTEGER PRIMARY KEY, v REAL);
+         INSERT INTO reals VALUES (1, 9e999);
+         INSERT INTO reals VALUES (2, -9e999);",
+    );
+    let values: Vec<f64> = dst
+        .prepare("SELECT v FROM reals ORDER BY id;")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, vec![f64::INFINITY, f64::NEG_INFINITY]);
+}
+
+#[test]
+fn integral_reals_keep_their_storage_class() {
+    let dst = roundtrip(
+        "CREATE TABLE reals (id INTEGER PRIMARY KEY, v);
+         INSERT INTO reals VALUES (1, 2.0);
+         INSERT INTO reals VALUES (2, 3);",
+    );
+    let types: Vec<String> = dst
+        .prepare("SELECT typeof(v) FROM reals ORDER BY id;")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(types, vec!["real", "integer"]);
+}
+
+#[test]
+fn text_and_blobs_round_trip() {
+    let dst = roundtrip(
+        "CREATE TABLE stuff (id INTEGER PRIMARY KEY, t TEXT, b BLOB);
+         INSERT INTO stuff VALUES (1, 'it''s; quoted', X'00FF');",
+    );
+    let (t, b): (String, Vec<u8>) = dst
+        .query_row("SELECT t, b FROM stuff;", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert_eq!(t, "it's; quoted");
+    assert_eq!(b, vec![0x00, 0xFF]);
+}
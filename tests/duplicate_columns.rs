@@ -0,0 +1,135 @@
+//! Test matrix for the duplicate-column guard: queries whose result sets
+//! repeat a column name must fail with `AmbiguousColumn` instead of
+//! silently misdeserializing (name-based row matching would pick one of
+//! the duplicates), while legitimate queries must pass untouched.
+
+use rusqlite::Connection;
+use rusqlite_helper::{RusqliteHelperError, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Account {
+    id: i64,
+    name: String,
+}
+
+fn setup() -> Connection {
+    let c = Connection::open_in_memory().unwrap();
+    c.execute_batch(
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT);
+         CREATE TABLE tags (id INTEGER PRIMARY KEY, account_id INTEGER, name TEXT);
+         INSERT INTO accounts VALUES (1, 'alice');
+         INSERT INTO tags VALUES (10, 1, 'rust');",
+    )
+    .unwrap();
+    c
+}
+
+/// Unwrap the context layer the query methods add around their errors.
+fn is_ambiguous(err: RusqliteHelperError) -> bool {
+    match err {
+        RusqliteHelperError::AmbiguousColumn(_) => true,
+        RusqliteHelperError::WithContext { source, .. } => is_ambiguous(*source),
+        _ => false,
+    }
+}
+
+#[test]
+fn field_alias_colliding_with_a_column_is_rejected() {
+    let c = setup();
+    // The mapping aliases id to "name", which the table already has: the
+    // result set is `id, name, name` and deserialization would silently
+    // pick one of the two.
+    let accounts =
+        Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT").with_field_column("name", "id");
+    let result: Result<Vec<Account>, _> = accounts.query(&c, "", []);
+    assert!(is_ambiguous(result.unwrap_err()));
+}
+
+#[test]
+fn two_fields_aliased_to_the_same_name_are_rejected() {
+    let c = setup();
+    let accounts = Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT")
+        .with_field_column("value", "id")
+        .with_field_column("value", "name");
+    let result: Result<Vec<Account>, _> = accounts.query(&c, "", []);
+    assert!(is_ambiguous(result.unwrap_err()));
+}
+
+#[test]
+fn star_join_views_are_deduplicated_by_sqlite() {
+    let c = setup();
+    // a.*, b.* with overlapping names: inside a VIEW, SQLite renames the
+    // duplicates (`name:1`), so the result set is unambiguous and the
+    // guard must not fire.
+    c.execute_batch(
+        "CREATE VIEW joined AS
+         SELECT accounts.*, tags.* FROM accounts
+         JOIN tags ON tags.account_id = accounts.id;",
+    )
+    .unwrap();
+    let view = Table::new("joined", "");
+    let rows: Vec<Account> = view.query(&c, "", []).unwrap();
+    assert_eq!(
+        rows,
+        vec![Account {
+            id: 1,
+            name: "alice".into()
+        }]
+    );
+}
+
+#[test]
+fn plain_table_query_is_not_a_false_positive() {
+    let c = setup();
+    let accounts = Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT");
+    let rows: Vec<Account> = accounts.query(&c, "", []).unwrap();
+    assert_eq!(
+        rows,
+        vec![Account {
+            id: 1,
+            name: "alice".into()
+        }]
+    );
+}
+
+#[test]
+fn join_query_selecting_one_side_is_not_a_false_positive() {
+    let c = setup();
+    let accounts = Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT");
+    let tags = Table::new("tags", "id INTEGER PRIMARY KEY, account_id INTEGER, name TEXT");
+    // Both tables have id and name, but join_query selects accounts.* only.
+    let rows: Vec<Account> = accounts
+        .join_query(
+            &c,
+            &tags,
+            "tags.account_id = accounts.id",
+            "WHERE tags.name = ?",
+            ["rust"],
+        )
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn disambiguated_aliases_pass() {
+    let c = setup();
+    c.execute_batch(
+        "CREATE VIEW disambiguated AS
+         SELECT accounts.id, accounts.name, tags.name AS tag FROM accounts
+         JOIN tags ON tags.account_id = accounts.id;",
+    )
+    .unwrap();
+    #[derive(Debug, Deserialize)]
+    struct Row {
+        id: i64,
+        name: String,
+        tag: String,
+    }
+    let view = Table::new("disambiguated", "");
+    let rows: Vec<Row> = view.query(&c, "", []).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].id, 1);
+    assert_eq!(rows[0].name, "alice");
+    assert_eq!(rows[0].tag, "rust");
+}
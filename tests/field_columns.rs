@@ -0,0 +1,152 @@
+//! Tests for [`Table::with_field_column`]: a Rust field stored under a
+//! differently named database column must work through every insert and
+//! query path, and generated columns must be dropped from field lists
+//! everywhere, not just in `Table::insert`.
+
+use rusqlite::Connection;
+use rusqlite_helper::{InsertOutcome, Table, UpsertOutcome};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Event {
+    id: i64,
+    kind: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EventFilter {
+    id: Option<i64>,
+    kind: Option<String>,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new("events", "id INTEGER PRIMARY KEY, category TEXT UNIQUE")
+        .with_pk("id")
+        .with_field_column("kind", "category");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn select_fetch_aliases_mapped_columns() {
+    let (c, table) = setup();
+    let row = Event { id: 1, kind: "login".into() };
+    table
+        .insert(
+            &c,
+            &row,
+            &["id", "kind"],
+            rusqlite_helper::InsertConflictResolution::None,
+        )
+        .unwrap();
+    let rows: Vec<Event> = table.select(&c).where_("id = ?", [1i64]).fetch().unwrap();
+    assert_eq!(rows, vec![row]);
+}
+
+#[test]
+fn insert_if_absent_maps_fields_to_columns() {
+    let (c, table) = setup();
+    let row = Event { id: 1, kind: "login".into() };
+    let inserted: Option<Event> = table
+        .insert_if_absent(&c, &row, &["id", "kind"], &["kind"])
+        .unwrap();
+    assert_eq!(inserted, Some(row));
+    let duplicate = Event { id: 2, kind: "login".into() };
+    let absent: Option<Event> = table
+        .insert_if_absent(&c, &duplicate, &["id", "kind"], &["kind"])
+        .unwrap();
+    assert_eq!(absent, None);
+}
+
+#[test]
+fn upsert_outcome_maps_fields_to_columns() {
+    let (c, table) = setup();
+    let row = Event { id: 1, kind: "login".into() };
+    let fields = &["id", "kind"];
+    assert_eq!(
+        table.upsert_outcome(&c, &row, fields, &["kind"]).unwrap(),
+        UpsertOutcome::Inserted
+    );
+    assert_eq!(
+        table.upsert_outcome(&c, &row, fields, &["kind"]).unwrap(),
+        UpsertOutcome::Unchanged
+    );
+    let moved = Event { id: 5, kind: "login".into() };
+    assert_eq!(
+        table.upsert_outcome(&c, &moved, fields, &["kind"]).unwrap(),
+        UpsertOutcome::Updated
+    );
+}
+
+#[test]
+fn insert_or_fetch_conflict_maps_fields_to_columns() {
+    let (c, table) = setup();
+    let row = Event { id: 1, kind: "login".into() };
+    let outcome: InsertOutcome<Event> = table
+        .insert_or_fetch_conflict(&c, &row, &["id", "kind"], &["kind"])
+        .unwrap();
+    assert!(matches!(outcome, InsertOutcome::Inserted));
+    let duplicate = Event { id: 2, kind: "login".into() };
+    let outcome: InsertOutcome<Event> = table
+        .insert_or_fetch_conflict(&c, &duplicate, &["id", "kind"], &["kind"])
+        .unwrap();
+    match outcome {
+        InsertOutcome::Conflicted(existing) => assert_eq!(existing, row),
+        InsertOutcome::Inserted => panic!("duplicate kind was inserted"),
+    }
+}
+
+#[test]
+fn query_by_example_maps_fields_to_columns() {
+    let (c, table) = setup();
+    for (id, kind) in [(1, "login"), (2, "logout")] {
+        table
+            .insert(
+                &c,
+                Event { id, kind: kind.into() },
+                &["id", "kind"],
+                rusqlite_helper::InsertConflictResolution::None,
+            )
+            .unwrap();
+    }
+    let filter = EventFilter { id: None, kind: Some("logout".into()) };
+    let rows: Vec<Event> = table.query_by_example(&c, &filter).unwrap();
+    assert_eq!(rows, vec![Event { id: 2, kind: "logout".into() }]);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Named {
+    id: i64,
+    name: String,
+    upper_name: String,
+}
+
+#[test]
+fn conflict_inserts_skip_generated_columns() {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new(
+        "named",
+        "id INTEGER PRIMARY KEY, name TEXT UNIQUE, \
+         upper_name TEXT GENERATED ALWAYS AS (UPPER(name)) STORED",
+    )
+    .with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    let row = Named { id: 1, name: "ada".into(), upper_name: "ADA".into() };
+    // `upper_name` is in the field list but cannot be written; the insert
+    // helpers must drop it instead of failing.
+    let fields = &["id", "name", "upper_name"];
+    let inserted: Option<Named> = table.insert_if_absent(&c, &row, fields, &["name"]).unwrap();
+    assert_eq!(inserted, Some(row));
+    let duplicate = Named { id: 2, name: "ada".into(), upper_name: "ADA".into() };
+    assert_eq!(
+        table
+            .upsert_outcome(&c, &duplicate, fields, &["name"])
+            .unwrap(),
+        UpsertOutcome::Updated
+    );
+}
@@ -0,0 +1,76 @@
+//! Tests for [`Table::create_if_fingerprint_changed`]: drift between app
+//! versions is caught via the stored fingerprint, and tables that predate
+//! fingerprint tracking are only adopted when their schema actually
+//! matches.
+
+use rusqlite::Connection;
+use rusqlite_helper::Table;
+
+const META: &str = "schema_meta";
+
+fn table() -> Table {
+    Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT").with_pk("id")
+}
+
+#[test]
+fn creates_then_leaves_unchanged_tables_alone() {
+    let c = Connection::open_in_memory().unwrap();
+    let table = table();
+    assert!(table.create_if_fingerprint_changed(&c, META).unwrap());
+    c.execute("INSERT INTO accounts VALUES (1, 'ada');", [])
+        .unwrap();
+    assert!(!table.create_if_fingerprint_changed(&c, META).unwrap());
+    let n: i64 = c
+        .query_row("SELECT COUNT(*) FROM accounts;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(n, 1);
+}
+
+#[test]
+fn recreates_on_fingerprint_mismatch() {
+    let c = Connection::open_in_memory().unwrap();
+    table().create_if_fingerprint_changed(&c, META).unwrap();
+    c.execute("INSERT INTO accounts VALUES (1, 'ada');", [])
+        .unwrap();
+    let changed = Table::new("accounts", "id INTEGER PRIMARY KEY, name TEXT, email TEXT");
+    assert!(changed.create_if_fingerprint_changed(&c, META).unwrap());
+    // The drifted table was dropped, data and all.
+    let n: i64 = c
+        .query_row("SELECT COUNT(*) FROM accounts;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(n, 0);
+    c.execute("INSERT INTO accounts VALUES (1, 'ada', 'a@b');", [])
+        .unwrap();
+}
+
+#[test]
+fn adopts_matching_table_without_recreating() {
+    let c = Connection::open_in_memory().unwrap();
+    // The table exists from before fingerprint tracking, with the same
+    // schema the def describes.
+    c.execute(
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT);",
+        [],
+    )
+    .unwrap();
+    c.execute("INSERT INTO accounts VALUES (1, 'ada');", [])
+        .unwrap();
+    assert!(!table().create_if_fingerprint_changed(&c, META).unwrap());
+    let n: i64 = c
+        .query_row("SELECT COUNT(*) FROM accounts;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(n, 1);
+}
+
+#[test]
+fn recreates_drifted_table_instead_of_adopting_it() {
+    let c = Connection::open_in_memory().unwrap();
+    // A pre-existing table whose schema does NOT match the def: adopting
+    // it silently would record the new fingerprint and hide the drift.
+    c.execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY);", [])
+        .unwrap();
+    assert!(table().create_if_fingerprint_changed(&c, META).unwrap());
+    // The recreated table carries the declared schema.
+    c.execute("INSERT INTO accounts VALUES (1, 'ada');", [])
+        .unwrap();
+}
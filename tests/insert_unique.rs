@@ -0,0 +1,57 @@
+//! Tests for [`Table::insert_unique`].
+
+use rusqlite::Connection;
+use rusqlite_helper::{RusqliteHelperError, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: i64,
+    email: String,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    // No UNIQUE constraint on email — insert_unique enforces it itself.
+    let table = Table::new("users", "id INTEGER PRIMARY KEY, email TEXT").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn inserts_then_rejects_the_duplicate() {
+    let (mut c, table) = setup();
+    let row = User {
+        id: 1,
+        email: "a@example.com".into(),
+    };
+    assert!(table
+        .insert_unique(&mut c, &row, &["id", "email"], &["email"])
+        .unwrap());
+
+    let duplicate = User {
+        id: 2,
+        email: "a@example.com".into(),
+    };
+    let result = table.insert_unique(&mut c, &duplicate, &["id", "email"], &["email"]);
+    match result {
+        Err(RusqliteHelperError::DuplicateKey { table, columns }) => {
+            assert_eq!(table, "users");
+            assert_eq!(columns, vec!["email".to_string()]);
+        }
+        other => panic!("expected DuplicateKey, got {other:?}"),
+    }
+    // The rejected insert left nothing behind.
+    let count: i64 = table.get_scalar(&c, "COUNT(*)", "", []).unwrap();
+    assert_eq!(count, 1);
+
+    let fresh = User {
+        id: 3,
+        email: "b@example.com".into(),
+    };
+    assert!(table
+        .insert_unique(&mut c, &fresh, &["id", "email"], &["email"])
+        .unwrap());
+}
@@ -0,0 +1,81 @@
+//! Tests for the JSONL export/import pair: `import_jsonl` must accept
+//! everything `export_jsonl` writes, BLOB columns included.
+
+#![cfg(feature = "json")]
+
+use rusqlite::Connection;
+use rusqlite_helper::{InsertConflictResolution, Table};
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new(
+        "files",
+        "id INTEGER PRIMARY KEY, name TEXT, data BLOB",
+    )
+    .with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn export_import_round_trips_blobs() {
+    let (c, table) = setup();
+    c.execute(
+        "INSERT INTO files VALUES (1, 'a.bin', X'0102FF'), (2, 'b.txt', NULL);",
+        [],
+    )
+    .unwrap();
+    let mut buffer = Vec::new();
+    assert_eq!(table.export_jsonl(&c, &mut buffer, "", []).unwrap(), 2);
+
+    c.execute("DELETE FROM files;", []).unwrap();
+    let imported = table
+        .import_jsonl(&c, buffer.as_slice(), InsertConflictResolution::None)
+        .unwrap();
+    assert_eq!(imported, 2);
+    let data: Vec<u8> = c
+        .query_row("SELECT data FROM files WHERE id = 1;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(data, vec![0x01, 0x02, 0xFF]);
+}
+
+#[test]
+fn insert_json_maps_byte_arrays_to_blob_columns() {
+    let (c, table) = setup();
+    table
+        .insert_json(
+            &c,
+            &serde_json::json!({"id": 1, "name": "a.bin", "data": [1, 2, 255]}),
+            InsertConflictResolution::None,
+        )
+        .unwrap();
+    let data: Vec<u8> = c
+        .query_row("SELECT data FROM files WHERE id = 1;", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(data, vec![1, 2, 255]);
+}
+
+#[test]
+fn insert_json_still_rejects_other_nested_values() {
+    let (c, table) = setup();
+    // An array aimed at a non-BLOB column keeps failing loudly ...
+    let err = table
+        .insert_json(
+            &c,
+            &serde_json::json!({"id": 1, "name": [1, 2]}),
+            InsertConflictResolution::None,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("nested value"));
+    // ... as does a non-byte array aimed at the BLOB column.
+    let err = table
+        .insert_json(
+            &c,
+            &serde_json::json!({"id": 1, "data": [1, 999]}),
+            InsertConflictResolution::None,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("array of bytes"));
+}
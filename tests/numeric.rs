@@ -0,0 +1,122 @@
+//! Round-trip tests for the numeric affinity behavior documented in the
+//! crate docs: `i64` and `f64` fields through `INTEGER` and `REAL` columns
+//! via the serde layer.
+
+use rusqlite::Connection;
+use rusqlite_helper::{InsertConflictResolution, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Numbers {
+    id: i64,
+    int_val: i64,
+    real_val: f64,
+}
+
+fn setup() -> (Connection, Table) {
+    let c = Connection::open_in_memory().unwrap();
+    let table = Table::new(
+        "numbers",
+        "id INTEGER PRIMARY KEY, int_val INTEGER, real_val REAL",
+    )
+    .with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    (c, table)
+}
+
+#[test]
+fn i64_and_f64_round_trip_exactly() {
+    let (c, table) = setup();
+    let rows = [
+        Numbers {
+            id: 1,
+            int_val: 0,
+            real_val: 0.1,
+        },
+        Numbers {
+            id: 2,
+            int_val: -42,
+            real_val: -1e300,
+        },
+        Numbers {
+            id: 3,
+            int_val: i64::MAX,
+            real_val: f64::MIN_POSITIVE,
+        },
+        Numbers {
+            id: 4,
+            int_val: i64::MIN,
+            real_val: std::f64::consts::PI,
+        },
+    ];
+    for row in &rows {
+        table
+            .insert(
+                &c,
+                row,
+                &["id", "int_val", "real_val"],
+                InsertConflictResolution::None,
+            )
+            .unwrap();
+    }
+    let loaded: Vec<Numbers> = table.query(&c, "ORDER BY id", []).unwrap();
+    assert_eq!(loaded, rows);
+}
+
+#[test]
+fn integer_column_keeps_integer_storage_class() {
+    let (c, table) = setup();
+    table
+        .insert(
+            &c,
+            Numbers {
+                id: 1,
+                int_val: 7,
+                real_val: 1.5,
+            },
+            &["id", "int_val", "real_val"],
+            InsertConflictResolution::None,
+        )
+        .unwrap();
+    let int_type: String = table
+        .get_scalar(&c, "typeof(int_val)", "WHERE id = 1", [])
+        .unwrap();
+    let real_type: String = table
+        .get_scalar(&c, "typeof(real_val)", "WHERE id = 1", [])
+        .unwrap();
+    assert_eq!(int_type, "integer");
+    assert_eq!(real_type, "real");
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FloatRow {
+    id: i64,
+    v: f64,
+}
+
+#[test]
+fn integral_float_in_integer_column_reads_back_as_f64() {
+    let (c, _) = setup();
+    let table = Table::new("floats", "id INTEGER PRIMARY KEY, v INTEGER").with_pk("id");
+    table
+        .create(&c, &rusqlite_helper::tables(&c).unwrap(), false)
+        .unwrap();
+    table
+        .insert(
+            &c,
+            FloatRow { id: 1, v: 2.0 },
+            &["id", "v"],
+            InsertConflictResolution::None,
+        )
+        .unwrap();
+    // INTEGER affinity stores the integral float as an integer ...
+    let stored: String = table
+        .get_scalar(&c, "typeof(v)", "WHERE id = 1", [])
+        .unwrap();
+    assert_eq!(stored, "integer");
+    // ... but the value still round-trips into the f64 field.
+    let loaded: Option<FloatRow> = table.load_by_pk(&c, 1).unwrap();
+    assert_eq!(loaded, Some(FloatRow { id: 1, v: 2.0 }));
+}
@@ -0,0 +1,62 @@
+//! Tests for the [`transform`] table-to-table helper, in particular that
+//! its rowid-keyset pagination covers negative rowids.
+
+use rusqlite::Connection;
+use rusqlite_helper::{transform, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    id: i64,
+    value: i64,
+}
+
+#[test]
+fn transform_reads_negative_rowids() {
+    let c = Connection::open_in_memory().unwrap();
+    let tables = rusqlite_helper::tables(&c).unwrap();
+    let src = Table::new("src", "id INTEGER PRIMARY KEY, value INTEGER").with_pk("id");
+    let dst = Table::new("dst", "id INTEGER PRIMARY KEY, value INTEGER").with_pk("id");
+    src.create(&c, &tables, false).unwrap();
+    dst.create(&c, &tables, false).unwrap();
+    // `id` aliases the rowid, so these rows sit at negative rowids.
+    c.execute_batch(
+        "INSERT INTO src VALUES (-5, 10);
+         INSERT INTO src VALUES (-1, 20);
+         INSERT INTO src VALUES (3, 30);",
+    )
+    .unwrap();
+    let (read, written) = transform(&c, &src, &dst, &["id", "value"], |row: Entry| {
+        Some(Entry {
+            id: row.id,
+            value: row.value * 2,
+        })
+    })
+    .unwrap();
+    assert_eq!((read, written), (3, 3));
+    let copied: Vec<Entry> = dst.query(&c, "ORDER BY id", []).unwrap();
+    assert_eq!(
+        copied.iter().map(|e| (e.id, e.value)).collect::<Vec<_>>(),
+        vec![(-5, 20), (-1, 40), (3, 60)]
+    );
+}
+
+#[test]
+fn transform_skips_rows_mapped_to_none() {
+    let c = Connection::open_in_memory().unwrap();
+    let tables = rusqlite_helper::tables(&c).unwrap();
+    let src = Table::new("src", "id INTEGER PRIMARY KEY, value INTEGER").with_pk("id");
+    let dst = Table::new("dst", "id INTEGER PRIMARY KEY, value INTEGER").with_pk("id");
+    src.create(&c, &tables, false).unwrap();
+    dst.create(&c, &tables, false).unwrap();
+    c.execute_batch(
+        "INSERT INTO src VALUES (1, 1);
+         INSERT INTO src VALUES (2, 2);",
+    )
+    .unwrap();
+    let (read, written) = transform(&c, &src, &dst, &["id", "value"], |row: Entry| {
+        (row.value % 2 == 0).then_some(row)
+    })
+    .unwrap();
+    assert_eq!((read, written), (2, 1));
+}